@@ -1,22 +1,75 @@
 use crate::script::{OutputStream, Script};
 use crate::{lock, send};
 use gtk::prelude::*;
+use std::process::Stdio;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command;
 use tokio::spawn;
+use tokio::task::JoinHandle;
+
+/// Poll interval, in milliseconds, for a PTY segment with no explicit interval.
+const DEFAULT_INTERVAL: u64 = 5000;
 
 #[derive(Debug)]
 enum DynamicStringSegment {
     Static(String),
     Dynamic(Script),
+    Pty(PtySegment),
+    Stream(StreamSegment),
+}
+
+/// A command run under a pseudo-terminal, so programs that only colour their
+/// output when attached to a TTY (`ip`, `git`, `systemctl`, …) still do so. The
+/// resulting ANSI stream is translated to Pango markup. Polled on `interval`.
+#[derive(Debug)]
+struct PtySegment {
+    interval: u64,
+    cmd: String,
+    interpreter: Option<String>,
+    env: Vec<(String, String)>,
+}
+
+/// A long-running command whose stdout is streamed into a segment, one line at
+/// a time, for the whole lifetime of the bar.
+#[derive(Debug)]
+struct StreamSegment {
+    cmd: String,
+    interpreter: Option<String>,
+    env: Vec<(String, String)>,
+    fallback: String,
 }
 
 /// A string with embedded scripts for dynamic content.
-pub struct DynamicString;
+pub struct DynamicString {
+    /// Handles to the per-segment tasks. Aborting them on drop tears down any
+    /// streaming child processes (spawned with `kill_on_drop`) so none leak.
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl Drop for DynamicString {
+    fn drop(&mut self) {
+        for handle in &self.handles {
+            handle.abort();
+        }
+    }
+}
 
 impl DynamicString {
     /// Creates a new dynamic string, based off the input template.
     /// Runs `f` with the compiled string each time one of the scripts updates.
     pub fn new<F>(input: &str, f: F) -> Self
+    where
+        F: FnMut(String) -> Continue + 'static,
+    {
+        Self::with_interpreter(input, None, f)
+    }
+
+    /// As [`new`](Self::new), but every dynamic segment defaults to running
+    /// under `interpreter` (e.g. `bash` or `python3`) unless it overrides the
+    /// choice with an `@interpreter` directive of its own.
+    pub fn with_interpreter<F>(input: &str, interpreter: Option<&str>, f: F) -> Self
     where
         F: FnMut(String) -> Continue + 'static,
     {
@@ -40,7 +93,7 @@ impl DynamicString {
                 let len = str.len();
 
                 (
-                    DynamicStringSegment::Dynamic(Script::from(str.as_str())),
+                    Self::parse_segment(str.as_str(), interpreter),
                     len + SKIP_BRACKETS,
                 )
             } else {
@@ -65,10 +118,12 @@ impl DynamicString {
         let label_parts = Arc::new(Mutex::new(Vec::new()));
         let (tx, rx) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
 
+        let mut handles = Vec::new();
+
         for (i, segment) in segments.into_iter().enumerate() {
             match segment {
                 DynamicStringSegment::Static(str) => {
-                    lock!(label_parts).push(str);
+                    lock!(label_parts).push(ansi::escape(&str));
                 }
                 DynamicStringSegment::Dynamic(script) => {
                     let tx = tx.clone();
@@ -77,20 +132,38 @@ impl DynamicString {
                     // insert blank value to preserve segment order
                     lock!(label_parts).push(String::new());
 
-                    spawn(async move {
+                    handles.push(spawn(async move {
                         script
                             .run(|(out, _)| {
                                 if let OutputStream::Stdout(out) = out {
                                     let mut label_parts = lock!(label_parts);
 
-                                    let _ = std::mem::replace(&mut label_parts[i], out);
+                                    let _ = std::mem::replace(&mut label_parts[i], ansi::to_pango(&out));
 
                                     let string = label_parts.join("");
                                     send!(tx, string);
                                 }
                             })
                             .await;
-                    });
+                    }));
+                }
+                DynamicStringSegment::Pty(segment) => {
+                    let tx = tx.clone();
+                    let label_parts = label_parts.clone();
+
+                    // insert blank value to preserve segment order
+                    lock!(label_parts).push(String::new());
+
+                    handles.push(spawn(segment.run(i, tx, label_parts)));
+                }
+                DynamicStringSegment::Stream(segment) => {
+                    let tx = tx.clone();
+                    let label_parts = label_parts.clone();
+
+                    // insert blank value to preserve segment order
+                    lock!(label_parts).push(String::new());
+
+                    handles.push(spawn(segment.run(i, tx, label_parts)));
                 }
             }
         }
@@ -103,7 +176,557 @@ impl DynamicString {
 
         rx.attach(None, f);
 
-        Self
+        Self { handles }
+    }
+
+    /// Parses a single dynamic segment token, pulling out any leading
+    /// `@interpreter`, `$KEY=VALUE` and `?fallback` directives.
+    ///
+    /// The directives sit between the mode prefix (`1000:` poll interval or `!`
+    /// watch flag) and the command itself, so `1000:@python3 $MONITOR=DP-1 ...`
+    /// runs the command under `python3` with `MONITOR` injected into the
+    /// child's environment only. The environment overrides are scoped to the
+    /// spawned process and never touch the bar's own environment.
+    ///
+    /// A leading `!` marks a streaming segment: the command is kept alive for
+    /// the whole session and each line it prints replaces the segment's value,
+    /// rather than being polled on an interval. A `!` directive (as in
+    /// `1000:!cmd`) instead runs the command under a pseudo-terminal so it
+    /// emits its coloured output.
+    fn parse_segment(raw: &str, interpreter: Option<&str>) -> DynamicStringSegment {
+        let (prefix, mut rest) = split_mode(raw);
+
+        let mut interpreter = interpreter.map(ToString::to_string);
+        let mut env = Vec::new();
+        let mut fallback = String::new();
+        let mut pty = false;
+
+        loop {
+            rest = rest.trim_start();
+            if let Some(tail) = rest.strip_prefix('@') {
+                let (interp, tail) = tail
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((tail, ""));
+                if interp.is_empty() {
+                    tracing::warn!("ignoring empty @interpreter directive in dynamic string segment '{raw}'");
+                } else {
+                    interpreter = Some(interp.to_string());
+                }
+                rest = tail;
+            } else if let Some(tail) = rest.strip_prefix('$') {
+                let (pair, tail) = tail.split_once(char::is_whitespace).unwrap_or((tail, ""));
+                match pair.split_once('=') {
+                    Some((key, value)) => env.push((key.to_string(), value.to_string())),
+                    None => tracing::warn!(
+                        "ignoring malformed ${pair} directive (expected $KEY=VALUE) in dynamic string segment '{raw}'"
+                    ),
+                }
+                rest = tail;
+            } else if let Some(tail) = rest.strip_prefix('?') {
+                let (value, tail) = tail.split_once(char::is_whitespace).unwrap_or((tail, ""));
+                fallback = value.to_string();
+                rest = tail;
+            } else if let Some(tail) = rest.strip_prefix('!') {
+                pty = true;
+                rest = tail;
+            } else {
+                break;
+            }
+        }
+
+        if prefix == "!" {
+            return DynamicStringSegment::Stream(StreamSegment {
+                cmd: rest.to_string(),
+                interpreter,
+                env,
+                fallback,
+            });
+        }
+
+        if pty {
+            return DynamicStringSegment::Pty(PtySegment {
+                interval: prefix
+                    .strip_suffix(':')
+                    .and_then(|p| p.parse().ok())
+                    .unwrap_or(DEFAULT_INTERVAL),
+                cmd: rest.to_string(),
+                interpreter,
+                env,
+            });
+        }
+
+        let mut script = Script::from(format!("{prefix}{rest}").as_str());
+        if let Some(interpreter) = interpreter {
+            script = script.with_interpreter(interpreter);
+        }
+        if !env.is_empty() {
+            script = script.with_env(env);
+        }
+        DynamicStringSegment::Dynamic(script)
+    }
+}
+
+impl PtySegment {
+    /// Builds the command, wrapping it in `script(1)` so the child runs under a
+    /// real pseudo-terminal. The interpreter is passed as the `SHELL` that
+    /// `script -c` invokes, and the environment overrides are scoped to the
+    /// child only.
+    fn command(&self) -> Command {
+        let mut command = Command::new("script");
+        command.arg("-qec").arg(&self.cmd).arg("/dev/null");
+        if let Some(interpreter) = &self.interpreter {
+            command.env("SHELL", interpreter);
+        }
+        for (key, value) in &self.env {
+            command.env(key, value);
+        }
+        command.stdout(Stdio::piped()).kill_on_drop(true);
+        command
+    }
+
+    /// Runs the segment, re-running the command on its interval.
+    async fn run(self, i: usize, tx: glib::Sender<String>, label_parts: Arc<Mutex<Vec<String>>>) {
+        loop {
+            if let Err(err) = self.run_once(i, &tx, &label_parts).await {
+                tracing::error!("PTY command '{}' failed: {err}", self.cmd);
+            }
+
+            tokio::time::sleep(Duration::from_millis(self.interval)).await;
+        }
+    }
+
+    /// Spawns the command under a pseudo-terminal, reads its raw bytes to EOF —
+    /// buffering any UTF-8 or escape sequence that straddles a read boundary —
+    /// and replaces the segment with the resulting markup.
+    async fn run_once(
+        &self,
+        i: usize,
+        tx: &glib::Sender<String>,
+        label_parts: &Arc<Mutex<Vec<String>>>,
+    ) -> std::io::Result<()> {
+        let mut child = self.command().spawn()?;
+
+        let mut converter = ansi::Converter::new();
+        let mut markup = String::new();
+        let mut buf = [0u8; 4096];
+        // Bytes read but not yet forming a complete UTF-8 sequence.
+        let mut remainder = Vec::new();
+
+        if let Some(mut stdout) = child.stdout.take() {
+            loop {
+                let read = stdout.read(&mut buf).await?;
+                if read == 0 {
+                    break;
+                }
+
+                remainder.extend_from_slice(&buf[..read]);
+                let valid_up_to = match std::str::from_utf8(&remainder) {
+                    Ok(_) => remainder.len(),
+                    Err(err) => err.valid_up_to(),
+                };
+
+                let decoded = String::from_utf8_lossy(&remainder[..valid_up_to]).into_owned();
+                remainder.drain(..valid_up_to);
+                markup.push_str(&converter.push(&decoded));
+            }
+        }
+
+        // Surface any trailing bytes that never formed valid UTF-8.
+        if !remainder.is_empty() {
+            markup.push_str(&converter.push(&String::from_utf8_lossy(&remainder)));
+        }
+
+        child.wait().await?;
+
+        let mut label_parts = lock!(label_parts);
+        let _ = std::mem::replace(&mut label_parts[i], markup);
+        send!(tx, label_parts.join(""));
+
+        Ok(())
+    }
+}
+
+impl StreamSegment {
+    /// The shortest delay before restarting a dead process.
+    const MIN_BACKOFF: Duration = Duration::from_millis(500);
+    /// The longest the restart delay is allowed to grow to.
+    const MAX_BACKOFF: Duration = Duration::from_secs(30);
+    /// How long a child must stay up before it counts as healthy and the
+    /// backoff resets.
+    const HEALTHY_AFTER: Duration = Duration::from_secs(5);
+
+    /// Builds the child process for this segment.
+    ///
+    /// `kill_on_drop` ensures that aborting the owning task — which happens when
+    /// the [`DynamicString`] is dropped — also reaps the child.
+    fn command(&self) -> Command {
+        let mut command = match &self.interpreter {
+            Some(interpreter) => {
+                let mut c = Command::new(interpreter);
+                c.arg("-c").arg(&self.cmd);
+                c
+            }
+            None => {
+                let mut c = Command::new("sh");
+                c.arg("-c").arg(&self.cmd);
+                c
+            }
+        };
+
+        for (key, value) in &self.env {
+            command.env(key, value);
+        }
+
+        command.stdout(Stdio::piped()).kill_on_drop(true);
+        command
+    }
+
+    /// Runs the streaming loop: spawn the child, push each stdout line into the
+    /// segment, and restart with exponential backoff if it dies, surfacing the
+    /// fallback string in the meantime.
+    async fn run(
+        self,
+        i: usize,
+        tx: glib::Sender<String>,
+        label_parts: Arc<Mutex<Vec<String>>>,
+    ) {
+        let mut backoff = Self::MIN_BACKOFF;
+
+        loop {
+            match self.command().spawn() {
+                Ok(mut child) => {
+                    let started = Instant::now();
+
+                    if let Some(stdout) = child.stdout.take() {
+                        let mut lines = BufReader::new(stdout).lines();
+                        while let Ok(Some(line)) = lines.next_line().await {
+                            let mut label_parts = lock!(label_parts);
+                            let _ = std::mem::replace(&mut label_parts[i], ansi::to_pango(&line));
+                            send!(tx, label_parts.join(""));
+                        }
+                    }
+
+                    let _ = child.wait().await;
+
+                    // Only a child that ran for a while counts as healthy; one
+                    // that exits immediately keeps the backoff growing so a
+                    // command that can never start isn't hammered.
+                    if started.elapsed() >= Self::HEALTHY_AFTER {
+                        backoff = Self::MIN_BACKOFF;
+                    }
+                }
+                Err(err) => {
+                    tracing::error!("failed to spawn streaming command '{}': {err}", self.cmd);
+                }
+            }
+
+            // The process exited: show the fallback and wait before retrying.
+            {
+                let mut label_parts = lock!(label_parts);
+                let _ = std::mem::replace(&mut label_parts[i], ansi::escape(&self.fallback));
+                send!(tx, label_parts.join(""));
+            }
+
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(Self::MAX_BACKOFF);
+        }
+    }
+}
+
+/// Splits the mode prefix (`1234:` poll interval or `!` streaming flag) off the
+/// front of a dynamic segment, returning `(prefix, remainder)`. Segments with
+/// no explicit mode yield an empty prefix.
+fn split_mode(raw: &str) -> (&str, &str) {
+    if let Some(rest) = raw.strip_prefix('!') {
+        (&raw[..1], rest)
+    } else if let Some(idx) = raw.find(':') {
+        if raw[..idx].chars().all(|c| c.is_ascii_digit()) && idx > 0 {
+            raw.split_at(idx + 1)
+        } else {
+            ("", raw)
+        }
+    } else {
+        ("", raw)
+    }
+}
+
+/// Conversion of raw terminal output into Pango markup.
+///
+/// Commands run under a PTY emit ANSI SGR escape sequences to colour their
+/// output. Pango labels don't understand ANSI, so we walk the byte stream,
+/// track the active colours and text attributes, and wrap each run of literal
+/// text in a `<span>` that mirrors them. Non-SGR control sequences (cursor
+/// moves, screen clears) are discarded rather than rendered.
+mod ansi {
+    use std::fmt::Write;
+
+    /// The 16 base ANSI colours, indexed by their `30`–`37` / `90`–`97` code.
+    const BASE_COLORS: [&str; 16] = [
+        "#000000", "#cc0000", "#4e9a06", "#c4a000", "#3465a4", "#75507b", "#06989a", "#d3d7cf",
+        "#555753", "#ef2929", "#8ae234", "#fce94f", "#729fcf", "#ad7fa8", "#34e2e2", "#eeeeec",
+    ];
+
+    /// A stateful ANSI→Pango converter.
+    ///
+    /// PTY output arrives in arbitrarily sized reads, so an escape sequence (or
+    /// the style it sets) can straddle a read boundary. The converter keeps the
+    /// active [`Style`] and any trailing partial escape between calls to
+    /// [`push`](Converter::push), so each chunk can be fed in as it is read.
+    #[derive(Default)]
+    pub struct Converter {
+        style: Style,
+        pending: String,
+    }
+
+    impl Converter {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Feeds a chunk of decoded output, returning the Pango markup for every
+        /// complete run it contains. A trailing incomplete escape sequence is
+        /// held back until a later call completes it.
+        pub fn push(&mut self, input: &str) -> String {
+            let mut data = std::mem::take(&mut self.pending);
+            data.push_str(input);
+
+            let chars = data.chars().collect::<Vec<_>>();
+            let mut out = String::with_capacity(data.len());
+            let mut text = String::new();
+            let mut i = 0;
+
+            while i < chars.len() {
+                if chars[i] != '\u{1b}' {
+                    text.push(chars[i]);
+                    i += 1;
+                    continue;
+                }
+
+                // An escape always precedes a style change, so flush the run.
+                flush(&mut out, &text, &self.style);
+                text.clear();
+
+                // Not enough bytes yet to tell what this escape is: buffer it.
+                if i + 1 >= chars.len() {
+                    self.pending = chars[i..].iter().collect();
+                    return out;
+                }
+
+                // OSC sequences (`ESC ] ... BEL`/`ST`) carry a payload such as a
+                // window title; consume the whole thing so it isn't rendered.
+                if chars[i + 1] == ']' {
+                    let mut j = i + 2;
+                    loop {
+                        if j >= chars.len() {
+                            self.pending = chars[i..].iter().collect();
+                            return out;
+                        }
+                        // BEL terminator.
+                        if chars[j] == '\u{07}' {
+                            j += 1;
+                            break;
+                        }
+                        // ST terminator (`ESC \`).
+                        if chars[j] == '\u{1b}' {
+                            if j + 1 >= chars.len() {
+                                self.pending = chars[i..].iter().collect();
+                                return out;
+                            }
+                            if chars[j + 1] == '\\' {
+                                j += 2;
+                                break;
+                            }
+                        }
+                        j += 1;
+                    }
+                    i = j;
+                    continue;
+                }
+
+                // Only CSI (`ESC [ ... final`) sequences are interpreted; any
+                // other escape is a two-byte sequence we skip.
+                if chars[i + 1] != '[' {
+                    i += 2;
+                    continue;
+                }
+
+                // Scan for the final byte; if it hasn't arrived, buffer and wait.
+                let mut j = i + 2;
+                while j < chars.len() && !('@'..='~').contains(&chars[j]) {
+                    j += 1;
+                }
+                if j >= chars.len() {
+                    self.pending = chars[i..].iter().collect();
+                    return out;
+                }
+
+                // Only SGR (`m`) sequences affect styling; drop the rest.
+                if chars[j] == 'm' {
+                    let params = chars[i + 2..j].iter().collect::<String>();
+                    apply_sgr(&mut self.style, &params);
+                }
+                i = j + 1;
+            }
+
+            flush(&mut out, &text, &self.style);
+            out
+        }
+    }
+
+    /// Text attributes tracked across an output stream.
+    #[derive(Default, Clone)]
+    struct Style {
+        foreground: Option<String>,
+        background: Option<String>,
+        bold: bool,
+        italic: bool,
+        underline: bool,
+    }
+
+    impl Style {
+        /// Whether any attribute is set, i.e. a `<span>` is needed.
+        fn is_set(&self) -> bool {
+            self.foreground.is_some()
+                || self.background.is_some()
+                || self.bold
+                || self.italic
+                || self.underline
+        }
+    }
+
+    /// Escapes the Pango/XML-significant characters in `text`.
+    pub fn escape(text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c {
+                '&' => out.push_str("&amp;"),
+                '<' => out.push_str("&lt;"),
+                '>' => out.push_str("&gt;"),
+                c => out.push(c),
+            }
+        }
+        out
+    }
+
+    /// Converts a complete string containing ANSI escape sequences into Pango
+    /// markup, escaping literal text and translating SGR colours/attributes into
+    /// spans. For output that arrives in chunks, drive a [`Converter`] directly.
+    pub fn to_pango(input: &str) -> String {
+        Converter::new().push(input)
+    }
+
+    /// Wraps the accumulated `text` in a span reflecting `style` and appends it.
+    fn flush(out: &mut String, text: &str, style: &Style) {
+        if text.is_empty() {
+            return;
+        }
+
+        let escaped = escape(text);
+        if !style.is_set() {
+            out.push_str(&escaped);
+            return;
+        }
+
+        out.push_str("<span");
+        if let Some(fg) = &style.foreground {
+            let _ = write!(out, " foreground=\"{fg}\"");
+        }
+        if let Some(bg) = &style.background {
+            let _ = write!(out, " background=\"{bg}\"");
+        }
+        if style.bold {
+            out.push_str(" weight=\"bold\"");
+        }
+        if style.italic {
+            out.push_str(" style=\"italic\"");
+        }
+        if style.underline {
+            out.push_str(" underline=\"single\"");
+        }
+        let _ = write!(out, ">{escaped}</span>");
+    }
+
+    /// Mutates `style` according to a single SGR parameter list (the text
+    /// between `ESC [` and `m`).
+    fn apply_sgr(style: &mut Style, params: &str) {
+        // An empty parameter list is equivalent to a reset.
+        if params.is_empty() {
+            *style = Style::default();
+            return;
+        }
+
+        let codes = params
+            .split(';')
+            .map(|p| p.parse::<u8>().unwrap_or(0))
+            .collect::<Vec<_>>();
+
+        let mut i = 0;
+        while i < codes.len() {
+            match codes[i] {
+                0 => *style = Style::default(),
+                1 => style.bold = true,
+                3 => style.italic = true,
+                4 => style.underline = true,
+                22 => style.bold = false,
+                23 => style.italic = false,
+                24 => style.underline = false,
+                30..=37 => style.foreground = Some(BASE_COLORS[(codes[i] - 30) as usize].into()),
+                39 => style.foreground = None,
+                40..=47 => style.background = Some(BASE_COLORS[(codes[i] - 40) as usize].into()),
+                49 => style.background = None,
+                90..=97 => style.foreground = Some(BASE_COLORS[(codes[i] - 90 + 8) as usize].into()),
+                100..=107 => {
+                    style.background = Some(BASE_COLORS[(codes[i] - 100 + 8) as usize].into());
+                }
+                // Extended colour: `38`/`48` followed by either `5;n` (256) or
+                // `2;r;g;b` (truecolour).
+                38 | 48 => {
+                    let target = if codes[i] == 38 {
+                        &mut style.foreground
+                    } else {
+                        &mut style.background
+                    };
+                    match codes.get(i + 1) {
+                        Some(5) => {
+                            if let Some(&n) = codes.get(i + 2) {
+                                *target = Some(color_256(n));
+                            }
+                            i += 2;
+                        }
+                        Some(2) => {
+                            if let (Some(&r), Some(&g), Some(&b)) =
+                                (codes.get(i + 2), codes.get(i + 3), codes.get(i + 4))
+                            {
+                                *target = Some(format!("#{r:02x}{g:02x}{b:02x}"));
+                            }
+                            i += 4;
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+
+    /// Resolves a 256-colour palette index to a `#rrggbb` string.
+    fn color_256(n: u8) -> String {
+        match n {
+            0..=15 => BASE_COLORS[n as usize].into(),
+            16..=231 => {
+                let n = n - 16;
+                let steps = [0u8, 95, 135, 175, 215, 255];
+                let r = steps[(n / 36) as usize];
+                let g = steps[(n / 6 % 6) as usize];
+                let b = steps[(n % 6) as usize];
+                format!("#{r:02x}{g:02x}{b:02x}")
+            }
+            232..=255 => {
+                let v = 8 + (n - 232) * 10;
+                format!("#{v:02x}{v:02x}{v:02x}")
+            }
+        }
     }
 }
 
@@ -111,6 +734,54 @@ impl DynamicString {
 mod tests {
     use super::*;
 
+    #[test]
+    fn plain_text_is_escaped() {
+        assert_eq!(ansi::to_pango("a & b < c"), "a &amp; b &lt; c");
+    }
+
+    #[test]
+    fn base_color_becomes_span() {
+        assert_eq!(
+            ansi::to_pango("\u{1b}[31mred\u{1b}[0m"),
+            "<span foreground=\"#cc0000\">red</span>"
+        );
+    }
+
+    #[test]
+    fn truecolor_and_attributes() {
+        assert_eq!(
+            ansi::to_pango("\u{1b}[1;38;2;255;128;0mx"),
+            "<span foreground=\"#ff8000\" weight=\"bold\">x</span>"
+        );
+    }
+
+    #[test]
+    fn non_sgr_sequences_are_stripped() {
+        assert_eq!(ansi::to_pango("a\u{1b}[2Kb"), "ab");
+    }
+
+    #[test]
+    fn osc_sequences_are_stripped() {
+        // A window-title OSC wrapped around some text leaves only the text.
+        assert_eq!(ansi::to_pango("\u{1b}]0;title\u{07}hi"), "hi");
+    }
+
+    #[test]
+    fn converter_buffers_escapes_split_across_reads() {
+        let mut converter = ansi::Converter::new();
+        // The SGR sequence is split mid-escape between the two reads.
+        assert_eq!(converter.push("\u{1b}[3"), "");
+        assert_eq!(converter.push("1mred"), "<span foreground=\"#cc0000\">red</span>");
+    }
+
+    #[test]
+    fn split_mode_splits_known_prefixes() {
+        assert_eq!(split_mode("1000:cmd"), ("1000:", "cmd"));
+        assert_eq!(split_mode("!cmd"), ("!", "cmd"));
+        assert_eq!(split_mode("cmd --flag"), ("", "cmd --flag"));
+        assert_eq!(split_mode("echo a:b"), ("", "echo a:b"));
+    }
+
     #[tokio::test]
     async fn test() {
         // TODO: see if we can run gtk tests in ci
@@ -119,7 +790,7 @@ mod tests {
             DynamicString::new(
                 "Uptime: {{1000:uptime -p | cut -d ' ' -f2-}}",
                 move |string| {
-                    label.set_label(&string);
+                    label.set_markup(&string);
                     Continue(true)
                 },
             );
@@ -0,0 +1,107 @@
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::time::sleep;
+use tracing::error;
+
+/// The default poll interval, in milliseconds, for a script with no explicit
+/// interval.
+const DEFAULT_INTERVAL: u64 = 5000;
+
+/// The output captured from one run of a script.
+#[derive(Debug)]
+pub enum OutputStream {
+    Stdout(String),
+    Stderr(String),
+}
+
+/// A shell command parsed from a dynamic-string segment, polled on an interval.
+#[derive(Debug, Clone)]
+pub struct Script {
+    cmd: String,
+    interval: u64,
+    interpreter: Option<String>,
+    env: Vec<(String, String)>,
+}
+
+impl Script {
+    /// Runs the command under `interpreter` (e.g. `bash` or `python3`) rather
+    /// than the default shell.
+    #[must_use]
+    pub fn with_interpreter(mut self, interpreter: String) -> Self {
+        self.interpreter = Some(interpreter);
+        self
+    }
+
+    /// Injects `env` into the spawned command's environment only, leaving the
+    /// parent process untouched.
+    #[must_use]
+    pub fn with_env(mut self, env: Vec<(String, String)>) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Builds the command, applying the interpreter and the child-scoped
+    /// environment overrides.
+    fn command(&self) -> Command {
+        let mut command = Command::new(self.interpreter.as_deref().unwrap_or("sh"));
+        command.arg("-c").arg(&self.cmd);
+        for (key, value) in &self.env {
+            command.env(key, value);
+        }
+        command
+    }
+
+    /// Runs the script on its interval, invoking `f` with each chunk of output
+    /// it produces.
+    pub async fn run<F>(&self, mut f: F)
+    where
+        F: FnMut((OutputStream, i32)),
+    {
+        loop {
+            match self.command().output().await {
+                Ok(output) => {
+                    let exit = output.status.code().unwrap_or(0);
+                    if !output.stdout.is_empty() {
+                        let stdout = String::from_utf8_lossy(&output.stdout).trim_end().to_string();
+                        f((OutputStream::Stdout(stdout), exit));
+                    }
+                    if !output.stderr.is_empty() {
+                        let stderr = String::from_utf8_lossy(&output.stderr).trim_end().to_string();
+                        f((OutputStream::Stderr(stderr), exit));
+                    }
+                }
+                Err(err) => error!("{err:?}"),
+            }
+
+            sleep(Duration::from_millis(self.interval)).await;
+        }
+    }
+}
+
+impl From<&str> for Script {
+    /// Parses a segment command of the form `interval:cmd`, falling back to
+    /// polling on the default interval. Streaming (`!`) segments are handled by
+    /// the caller and never reach here.
+    fn from(str: &str) -> Self {
+        let mut interval = DEFAULT_INTERVAL;
+
+        let cmd = if let Some((prefix, cmd)) = str.split_once(':') {
+            match prefix.parse::<u64>() {
+                Ok(parsed) => {
+                    interval = parsed;
+                    cmd.to_string()
+                }
+                Err(_) => str.to_string(),
+            }
+        } else {
+            str.to_string()
+        };
+
+        Self {
+            cmd,
+            interval,
+            interpreter: None,
+            env: Vec::new(),
+        }
+    }
+}